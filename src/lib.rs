@@ -1,7 +1,12 @@
+use std::collections::{HashMap, VecDeque};
 use std::fs;
 use std::fs::File;
-use std::io::{BufRead, BufReader, stdin};
+use std::io::{BufRead, BufReader, Cursor, Read, stdin};
 use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
 
 use clap::Parser;
 use regex::{Regex, RegexBuilder};
@@ -23,18 +28,283 @@ pub struct Cli {
     invert_match: bool,
     #[arg(short, long, help = "Case insensitive")]
     insensitive: bool,
+    #[arg(
+        short = 'S',
+        long = "smart-case",
+        help = "Case insensitive unless the pattern contains an uppercase letter"
+    )]
+    smart_case: bool,
+    #[arg(
+        short,
+        long = "glob",
+        value_name = "GLOB",
+        help = "Include/exclude (prefix with !) files matching glob"
+    )]
+    globs: Vec<String>,
+    #[arg(skip)]
+    include_globs: Vec<Regex>,
+    #[arg(skip)]
+    exclude_globs: Vec<Regex>,
+    #[arg(
+        short = 'j',
+        long = "threads",
+        value_name = "THREADS",
+        help = "Number of worker threads for recursive search [default: number of CPUs]"
+    )]
+    threads: Option<usize>,
+    #[arg(long, help = "Don't sniff a UTF-8/UTF-16 BOM; read files as raw bytes")]
+    no_bom_sniff: bool,
+    #[arg(long, help = "Search hidden files and directories")]
+    hidden: bool,
+    #[arg(long, help = "Don't respect .gitignore/.ignore files")]
+    no_ignore: bool,
+    #[arg(short = 'n', long = "line-number", help = "Print line numbers")]
+    line_number: bool,
+    #[arg(
+        short = 'A',
+        long = "after-context",
+        value_name = "NUM",
+        help = "Print NUM lines of trailing context after each match"
+    )]
+    after_context: Option<usize>,
+    #[arg(
+        short = 'B',
+        long = "before-context",
+        value_name = "NUM",
+        help = "Print NUM lines of leading context before each match"
+    )]
+    before_context: Option<usize>,
+    #[arg(
+        short = 'C',
+        long = "context",
+        value_name = "NUM",
+        help = "Print NUM lines of context before and after each match"
+    )]
+    context: Option<usize>,
 }
 
 pub fn get_cli() -> MyResult<Cli> {
     let mut cli = Cli::parse();
+
+    let case_insensitive = cli.insensitive
+        || (cli.smart_case && !pattern_has_uppercase_char(&cli.pattern.to_string()));
+
     cli.pattern = RegexBuilder::new(&cli.pattern.to_string())
-        .case_insensitive(cli.insensitive)
+        .case_insensitive(case_insensitive)
         .build()?;
 
+    for glob in &cli.globs {
+        let (exclude, raw) = match glob.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, glob.as_str()),
+        };
+
+        // a glob with no `/` should match the file anywhere in the tree,
+        // not just at the search root
+        let pattern = if raw.contains('/') {
+            raw.to_string()
+        } else {
+            format!("**/{}", raw)
+        };
+
+        let regex = from_glob(&pattern)?;
+        if exclude {
+            cli.exclude_globs.push(regex);
+        } else {
+            cli.include_globs.push(regex);
+        }
+    }
+
     Ok(cli)
 }
 
-fn find_files(paths: &[String], recursive: bool) -> Vec<MyResult<String>> {
+/// A pattern has a "significant" uppercase character if it contains an
+/// uppercase letter that isn't part of an escape sequence like `\W` or
+/// `\S` (mirrors the smart-case heuristic used by fd and ripgrep).
+fn pattern_has_uppercase_char(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            chars.next();
+            continue;
+        }
+
+        if c.is_uppercase() {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Translate a glob pattern into an anchored regex: `*` matches within a
+/// path segment, `**` crosses segments, `?` matches a single non-`/`
+/// character, and `[...]`/`[!...]` classes pass through (negated).
+fn from_glob(glob: &str) -> MyResult<Regex> {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => {
+                if chars.peek() == Some(&'*') {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        // `**/` also matches zero leading directories, so a
+                        // top-level entry still matches
+                        chars.next();
+                        regex.push_str("(?:.*/)?");
+                    } else {
+                        regex.push_str(".*");
+                    }
+                } else {
+                    regex.push_str("[^/]*");
+                }
+            }
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                regex.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    regex.push('^');
+                }
+                for next in chars.by_ref() {
+                    if next == ']' {
+                        regex.push(']');
+                        break;
+                    }
+                    regex.push(next);
+                }
+            }
+            '.' | '+' | '(' | ')' | '|' | '^' | '$' | '{' | '}' | '\\' => {
+                regex.push('\\');
+                regex.push(c);
+            }
+            _ => regex.push(c),
+        }
+    }
+    regex.push('$');
+
+    Ok(RegexBuilder::new(&regex).build()?)
+}
+
+/// A path survives if it matches at least one include glob (or there are
+/// none) and matches none of the exclude globs.
+fn matches_globs(path: &str, include_globs: &[Regex], exclude_globs: &[Regex]) -> bool {
+    let path = path.replace('\\', "/");
+
+    if exclude_globs.iter().any(|re| re.is_match(&path)) {
+        return false;
+    }
+
+    include_globs.is_empty() || include_globs.iter().any(|re| re.is_match(&path))
+}
+
+/// A single line of a `.gitignore`/`.ignore` file, compiled to a regex
+/// relative to the directory the file lives in.
+struct IgnoreRule {
+    regex: Regex,
+    negate: bool,
+    dir_only: bool,
+}
+
+fn parse_ignore_line(line: &str) -> Option<IgnoreRule> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negate, rest) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+
+    let dir_only = rest.len() > 1 && rest.ends_with('/');
+    let core = rest.trim_end_matches('/');
+    let anchored = core.starts_with('/');
+    let core = core.trim_start_matches('/');
+
+    // a pattern with no `/` matches at any depth, like our `--glob` filters;
+    // one with a `/` (leading or internal) is anchored to this file's directory
+    let glob_pattern = if anchored || core.contains('/') {
+        core.to_string()
+    } else {
+        format!("**/{}", core)
+    };
+
+    let regex = from_glob(&glob_pattern).ok()?;
+
+    Some(IgnoreRule { regex, negate, dir_only })
+}
+
+fn load_ignore_rules(dir: &Path) -> Vec<IgnoreRule> {
+    [".gitignore", ".ignore"]
+        .iter()
+        .filter_map(|name| fs::read_to_string(dir.join(name)).ok())
+        .flat_map(|contents| contents.lines().filter_map(parse_ignore_line).collect::<Vec<_>>())
+        .collect()
+}
+
+/// Whether a `WalkDir` entry should be pruned: hidden (unless `--hidden`),
+/// or matched by an active `.gitignore`/`.ignore` rule (unless
+/// `--no-ignore`). Ignore files are read lazily, once per directory, and
+/// rules apply from the walk root down so a more specific rule (including
+/// a negated re-include) wins, mirroring git's own precedence.
+fn is_ignored(
+    entry: &walkdir::DirEntry,
+    root: &Path,
+    ignore_cache: &mut HashMap<PathBuf, Vec<IgnoreRule>>,
+    hidden: bool,
+    no_ignore: bool,
+) -> bool {
+    if entry.path() == root {
+        return false;
+    }
+
+    if !hidden && entry.file_name().to_string_lossy().starts_with('.') {
+        return true;
+    }
+
+    if no_ignore {
+        return false;
+    }
+
+    let parent = entry.path().parent().unwrap_or(root);
+    let mut dirs: Vec<&Path> = parent.ancestors().take_while(|p| p.starts_with(root)).collect();
+    dirs.reverse();
+
+    let is_dir = entry.file_type().is_dir();
+    let mut ignored = false;
+
+    for dir in dirs {
+        let rules = ignore_cache
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| load_ignore_rules(dir));
+
+        let rel = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+        let rel = rel.to_string_lossy().replace('\\', "/");
+
+        for rule in rules.iter() {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+            if rule.regex.is_match(&rel) {
+                ignored = !rule.negate;
+            }
+        }
+    }
+
+    ignored
+}
+
+fn find_files(
+    paths: &[String],
+    recursive: bool,
+    include_globs: &[Regex],
+    exclude_globs: &[Regex],
+    hidden: bool,
+    no_ignore: bool,
+) -> Vec<MyResult<String>> {
     let mut results: Vec<MyResult<String>> = vec![];
 
     for path in paths {
@@ -52,10 +322,23 @@ fn find_files(paths: &[String], recursive: bool) -> Vec<MyResult<String>> {
                         continue;
                     }
 
+                    let root = Path::new(path);
+                    let mut ignore_cache: HashMap<PathBuf, Vec<IgnoreRule>> = HashMap::new();
+
                     for entry in WalkDir::new(path)
                         .into_iter()
+                        .filter_entry(|e| {
+                            !is_ignored(e, root, &mut ignore_cache, hidden, no_ignore)
+                        })
                         .flatten()
                         .filter(|e| e.file_type().is_file())
+                        .filter(|e| {
+                            matches_globs(
+                                &e.path().display().to_string(),
+                                include_globs,
+                                exclude_globs,
+                            )
+                        })
                     {
                         results.push(Ok(entry.path().display().to_string()))
                     }
@@ -71,72 +354,351 @@ fn find_files(paths: &[String], recursive: bool) -> Vec<MyResult<String>> {
     results
 }
 
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+/// Which byte-order mark, if any, a file starts with.
+enum Bom {
+    None,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// Peek the reader's leading bytes for a BOM and consume it so the caller
+/// doesn't see it in the stream.
+fn detect_bom(reader: &mut dyn BufRead) -> MyResult<Bom> {
+    let buf = reader.fill_buf()?;
+
+    let (bom, len) = if buf.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        (Bom::Utf8, 3)
+    } else if buf.starts_with(&[0xFF, 0xFE]) {
+        (Bom::Utf16Le, 2)
+    } else if buf.starts_with(&[0xFE, 0xFF]) {
+        (Bom::Utf16Be, 2)
+    } else {
+        (Bom::None, 0)
+    };
+
+    reader.consume(len);
+
+    Ok(bom)
+}
+
+/// Transcode UTF-16 bytes to a UTF-8 `String`, substituting the Unicode
+/// replacement character for any unpaired surrogate.
+fn decode_utf16_bytes(bytes: &[u8], little_endian: bool) -> String {
+    let units = bytes.chunks_exact(2).map(|pair| {
+        if little_endian {
+            u16::from_le_bytes([pair[0], pair[1]])
+        } else {
+            u16::from_be_bytes([pair[0], pair[1]])
+        }
+    });
+
+    char::decode_utf16(units)
+        .map(|r| r.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+fn open(filename: &str, no_bom_sniff: bool) -> MyResult<Box<dyn BufRead>> {
+    let mut reader: Box<dyn BufRead> = match filename {
+        "-" => Box::new(BufReader::new(stdin())),
+        _ => Box::new(BufReader::new(File::open(filename)?)),
+    };
+
+    if no_bom_sniff {
+        return Ok(reader);
     }
+
+    let little_endian = match detect_bom(reader.as_mut())? {
+        Bom::Utf8 | Bom::None => return Ok(reader),
+        Bom::Utf16Le => true,
+        Bom::Utf16Be => false,
+    };
+
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    let text = decode_utf16_bytes(&bytes, little_endian);
+
+    Ok(Box::new(Cursor::new(text.into_bytes())))
+}
+
+/// One line of search output, tagged with its 1-based line number so it
+/// can be labeled `lineno:` (a match) or `lineno-` (context) like GNU grep.
+enum OutputLine {
+    Match(usize, String),
+    Context(usize, String),
+    /// A `--` marker grep prints between two non-adjacent context groups.
+    Separator,
+}
+
+/// Push `entry` onto `output`, inserting a `--` separator first if there's
+/// a gap since the last emitted line. Only tracked when context is on,
+/// since grep never separates plain (non-context) match output.
+fn emit_line(
+    output: &mut Vec<OutputLine>,
+    last_emitted: &mut Option<usize>,
+    lineno: usize,
+    entry: OutputLine,
+    context_enabled: bool,
+) {
+    if context_enabled {
+        if let Some(prev) = *last_emitted {
+            if lineno > prev + 1 {
+                output.push(OutputLine::Separator);
+            }
+        }
+        *last_emitted = Some(lineno);
+    }
+
+    output.push(entry);
 }
 
 fn find_lines<T: BufRead>(
     mut file: T,
     pattern: &Regex,
     invert_match: bool,
-) -> MyResult<Vec<String>>
-{
-    let mut result: Vec<String> = vec![];
-
+    before_context: usize,
+    after_context: usize,
+) -> MyResult<(usize, Vec<OutputLine>)> {
+    let context_enabled = before_context > 0 || after_context > 0;
+
+    let mut output: Vec<OutputLine> = vec![];
+    let mut match_count = 0;
+    let mut last_emitted: Option<usize> = None;
+    let mut before_buf: VecDeque<(usize, String)> = VecDeque::new();
+    let mut after_remaining = 0;
+
+    let mut lineno = 0;
     let mut line = String::new();
     loop {
         let bytes = file.read_line(&mut line)?;
         if bytes == 0 {
             break;
         }
+        lineno += 1;
+
+        let is_match = invert_match ^ pattern.is_match(&line);
+        let text = mem::take(&mut line);
+
+        if is_match {
+            match_count += 1;
+
+            for (bl_no, bl_text) in before_buf.drain(..) {
+                emit_line(
+                    &mut output,
+                    &mut last_emitted,
+                    bl_no,
+                    OutputLine::Context(bl_no, bl_text),
+                    context_enabled,
+                );
+            }
 
-        if invert_match ^ pattern.is_match(&line) {
-            result.push(mem::take(&mut line));
+            emit_line(
+                &mut output,
+                &mut last_emitted,
+                lineno,
+                OutputLine::Match(lineno, text),
+                context_enabled,
+            );
+            after_remaining = after_context;
+        } else if after_remaining > 0 {
+            after_remaining -= 1;
+            emit_line(
+                &mut output,
+                &mut last_emitted,
+                lineno,
+                OutputLine::Context(lineno, text),
+                context_enabled,
+            );
+        } else if before_context > 0 {
+            before_buf.push_back((lineno, text));
+            while before_buf.len() > before_context {
+                before_buf.pop_front();
+            }
         }
 
         line.clear();
     }
 
-    Ok(result)
+    Ok((match_count, output))
+}
+
+/// The outcome of searching a single file. Workers send it back paired
+/// with the file's original index so output can be reassembled in input
+/// order.
+enum FileResult {
+    Error(String),
+    Lines {
+        filename: String,
+        match_count: usize,
+        lines: Vec<OutputLine>,
+    },
+}
+
+fn search_file(
+    filename: Result<String, String>,
+    pattern: &Regex,
+    invert_match: bool,
+    no_bom_sniff: bool,
+    before_context: usize,
+    after_context: usize,
+) -> FileResult {
+    let filename = match filename {
+        Ok(filename) => filename,
+        Err(e) => return FileResult::Error(e),
+    };
+
+    match open(&filename, no_bom_sniff) {
+        Err(e) => FileResult::Error(format!("{}: {}", filename, e)),
+        Ok(file) => match find_lines(file, pattern, invert_match, before_context, after_context) {
+            Ok((match_count, lines)) => FileResult::Lines {
+                filename,
+                match_count,
+                lines,
+            },
+            Err(e) => FileResult::Error(format!("{}: {}", filename, e)),
+        },
+    }
+}
+
+/// Render a single output line with grep's `name:lineno:text` /
+/// `name-lineno-text` prefixing, omitting whichever parts are disabled.
+fn print_line(filename: &str, multiple_files: bool, line_number: bool, lineno: usize, sep: char, text: &str) {
+    let mut prefix = String::new();
+
+    if multiple_files {
+        prefix.push_str(filename);
+    }
+
+    if line_number {
+        if !prefix.is_empty() {
+            prefix.push(sep);
+        }
+        prefix.push_str(&lineno.to_string());
+    }
+
+    if prefix.is_empty() {
+        print!("{}", text);
+    } else {
+        print!("{}{}{}", prefix, sep, text);
+    }
+}
+
+fn print_result(result: FileResult, multiple_files: bool, count: bool, line_number: bool) {
+    match result {
+        FileResult::Error(e) => eprintln!("{}", e),
+        FileResult::Lines {
+            filename,
+            match_count,
+            lines,
+        } => {
+            if count {
+                if multiple_files {
+                    println!("{}:{}", filename, match_count);
+                } else {
+                    println!("{}", match_count);
+                }
+                return;
+            }
+
+            for entry in lines {
+                match entry {
+                    OutputLine::Separator => println!("--"),
+                    OutputLine::Match(lineno, text) => {
+                        print_line(&filename, multiple_files, line_number, lineno, ':', &text)
+                    }
+                    OutputLine::Context(lineno, text) => {
+                        print_line(&filename, multiple_files, line_number, lineno, '-', &text)
+                    }
+                }
+            }
+        }
+    }
 }
 
 pub fn run(cli: Cli) -> MyResult<()> {
-    let filenames = find_files(&cli.files, cli.recursive);
+    let filenames = find_files(
+        &cli.files,
+        cli.recursive,
+        &cli.include_globs,
+        &cli.exclude_globs,
+        cli.hidden,
+        cli.no_ignore,
+    );
     let file_count = filenames.len();
+    let multiple_files = file_count > 1;
+
+    let before_context = cli.before_context.or(cli.context).unwrap_or(0);
+    let after_context = cli.after_context.or(cli.context).unwrap_or(0);
+
+    let mut results: Vec<Option<FileResult>> = (0..file_count).map(|_| None).collect();
+
+    // stdin can't be shared across threads, so it's searched on the main
+    // thread; everything else is handed to the worker pool. `Box<dyn Error>`
+    // isn't `Send`, so errors are stringified before crossing that boundary.
+    let mut parallel_jobs: Vec<(usize, Result<String, String>)> = vec![];
+    for (i, filename) in filenames.into_iter().enumerate() {
+        if matches!(&filename, Ok(name) if name == "-") {
+            results[i] = Some(search_file(
+                filename.map_err(|e| e.to_string()),
+                &cli.pattern,
+                cli.invert_match,
+                cli.no_bom_sniff,
+                before_context,
+                after_context,
+            ));
+        } else {
+            parallel_jobs.push((i, filename.map_err(|e| e.to_string())));
+        }
+    }
 
-    for filename in filenames {
-        match filename {
-            Err(e) => eprintln!("{}", e),
-            Ok(filename) => {
-                match open(&filename) {
-                    Err(e) => eprintln!("{}: {}", filename, e),
-                    Ok(file) => {
-                        let lines = find_lines(file, &cli.pattern, cli.invert_match)?;
-
-                        if file_count > 1 {
-                            if cli.count {
-                                println!("{}:{}", filename, lines.len());
-                            } else {
-                                for line in lines {
-                                    print!("{}:{}", filename, line);
-                                }
-                            }
-                        } else {
-                            if cli.count {
-                                println!("{}", lines.len());
-                            } else {
-                                for line in lines {
-                                    print!("{}", line);
-                                }
+    if !parallel_jobs.is_empty() {
+        let num_threads = cli
+            .threads
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()))
+            .max(1)
+            .min(parallel_jobs.len());
+
+        let queue = Mutex::new(parallel_jobs.into_iter());
+        let (tx, rx) = mpsc::channel();
+
+        thread::scope(|scope| {
+            for _ in 0..num_threads {
+                let queue = &queue;
+                let tx = tx.clone();
+                let pattern = &cli.pattern;
+                let invert_match = cli.invert_match;
+                let no_bom_sniff = cli.no_bom_sniff;
+
+                scope.spawn(move || loop {
+                    let job = queue.lock().unwrap().next();
+                    match job {
+                        None => break,
+                        Some((i, filename)) => {
+                            let result = search_file(
+                                filename,
+                                pattern,
+                                invert_match,
+                                no_bom_sniff,
+                                before_context,
+                                after_context,
+                            );
+                            if tx.send((i, result)).is_err() {
+                                break;
                             }
                         }
                     }
-                }
+                });
             }
-        }
+            drop(tx);
+
+            for (i, result) in rx {
+                results[i] = Some(result);
+            }
+        });
+    }
+
+    for result in results.into_iter().flatten() {
+        print_result(result, multiple_files, cli.count, cli.line_number);
     }
 
     Ok(())
@@ -150,21 +712,24 @@ mod tests {
     use rand::Rng;
     use regex::{Regex, RegexBuilder};
 
-    use super::{find_files, find_lines};
+    use super::{
+        decode_utf16_bytes, find_files, find_lines, from_glob, matches_globs, parse_ignore_line,
+        pattern_has_uppercase_char, OutputLine,
+    };
 
     #[test]
     fn test_find_files() {
-        let files = find_files(&["./tests/inputs/fox.txt".to_string()], false);
+        let files = find_files(&["./tests/inputs/fox.txt".to_string()], false, &[], &[], false, false);
         assert_eq!(files.len(), 1);
         assert_eq!(files[0].as_ref().unwrap(), "./tests/inputs/fox.txt");
 
-        let files = find_files(&["./tests/inputs".to_string()], false);
+        let files = find_files(&["./tests/inputs".to_string()], false, &[], &[], false, false);
         assert_eq!(files.len(), 1);
         if let Err(e) = &files[0] {
             assert_eq!(e.to_string(), "./tests/inputs is a directory")
         }
 
-        let res = find_files(&["./tests/inputs".to_string()], true);
+        let res = find_files(&["./tests/inputs".to_string()], true, &[], &[], false, false);
         let mut files: Vec<String> = res
             .iter()
             .map(|r| r.as_ref().unwrap().replace("\\", "/"))
@@ -186,25 +751,106 @@ mod tests {
             .take(7)
             .map(char::from)
             .collect();
-        let files = find_files(&[bad], false);
+        let files = find_files(&[bad], false, &[], &[], false, false);
         assert_eq!(files.len(), 1);
         assert!(files[0].is_err());
     }
 
+    #[test]
+    fn test_parse_ignore_line() {
+        assert!(parse_ignore_line("# a comment").is_none());
+        assert!(parse_ignore_line("").is_none());
+
+        let rule = parse_ignore_line("target/").unwrap();
+        assert!(!rule.negate);
+        assert!(rule.dir_only);
+        assert!(rule.regex.is_match("target"));
+
+        let rule = parse_ignore_line("!keep.txt").unwrap();
+        assert!(rule.negate);
+        assert!(rule.regex.is_match("keep.txt"));
+        assert!(rule.regex.is_match("nested/keep.txt"));
+
+        let rule = parse_ignore_line("/build").unwrap();
+        assert!(rule.regex.is_match("build"));
+        assert!(!rule.regex.is_match("nested/build"));
+    }
+
+    #[test]
+    fn test_find_files_respects_top_level_gitignore() {
+        let root = std::env::temp_dir().join("grepr_test_find_files_respects_top_level_gitignore");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        std::fs::write(root.join("target").join("artifact.txt"), "SECRET\n").unwrap();
+        std::fs::write(root.join("keep.txt"), "SECRET\n").unwrap();
+
+        let files = find_files(&[root.display().to_string()], true, &[], &[], false, false);
+        let files: Vec<String> = files
+            .iter()
+            .map(|r| r.as_ref().unwrap().replace('\\', "/"))
+            .collect();
+
+        assert!(files.iter().any(|f| f.ends_with("keep.txt")));
+        assert!(!files.iter().any(|f| f.contains("target")));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_decode_utf16_bytes() {
+        let le = "hi".encode_utf16().flat_map(u16::to_le_bytes).collect::<Vec<u8>>();
+        assert_eq!(decode_utf16_bytes(&le, true), "hi");
+
+        let be = "hi".encode_utf16().flat_map(u16::to_be_bytes).collect::<Vec<u8>>();
+        assert_eq!(decode_utf16_bytes(&be, false), "hi");
+    }
+
+    #[test]
+    fn test_pattern_has_uppercase_char() {
+        assert!(!pattern_has_uppercase_char("lorem"));
+        assert!(pattern_has_uppercase_char("Lorem"));
+        assert!(!pattern_has_uppercase_char(r"\W\S"));
+        assert!(pattern_has_uppercase_char(r"\Wfoo\SBar"));
+    }
+
+    #[test]
+    fn test_from_glob() {
+        let re = from_glob("**/*.rs").unwrap();
+        assert!(re.is_match("src/lib.rs"));
+        assert!(re.is_match("lib.rs"));
+        assert!(!re.is_match("lib.rs.bak"));
+    }
+
+    #[test]
+    fn test_matches_globs() {
+        let include = vec![from_glob("**/*.txt").unwrap()];
+        let exclude = vec![from_glob("**/nobody.txt").unwrap()];
+
+        assert!(matches_globs("./tests/inputs/fox.txt", &include, &exclude));
+        assert!(!matches_globs(
+            "./tests/inputs/nobody.txt",
+            &include,
+            &exclude
+        ));
+        assert!(!matches_globs("./tests/inputs/fox.rs", &include, &exclude));
+        assert!(matches_globs("./tests/inputs/fox.txt", &[], &exclude));
+    }
+
     #[test]
     fn test_find_lines() {
         let text = b"Lorem\nIpsum\r\nDOLOR";
 
         // or というパターンは、Lorem という1行にマッチする
         let re1 = Regex::new("or").unwrap();
-        let matches = find_lines(Cursor::new(&text), &re1, false);
+        let matches = find_lines(Cursor::new(&text), &re1, false, 0, 0);
         assert!(matches.is_ok());
-        assert_eq!(matches.unwrap().len(), 1);
+        assert_eq!(matches.unwrap().0, 1);
 
         // マッチを反転させた場合、残りの2行にマッチする
-        let matches = find_lines(Cursor::new(&text), &re1, true);
+        let matches = find_lines(Cursor::new(&text), &re1, true, 0, 0);
         assert!(matches.is_ok());
-        assert_eq!(matches.unwrap().len(), 2);
+        assert_eq!(matches.unwrap().0, 2);
 
         let re2 = RegexBuilder::new("or")
             .case_insensitive(true)
@@ -212,13 +858,43 @@ mod tests {
             .unwrap();
 
         // 大文字と小文字を区別しないので、Lorem と DOLOR の2行にマッチする
-        let matches = find_lines(Cursor::new(&text), &re2, false);
+        let matches = find_lines(Cursor::new(&text), &re2, false, 0, 0);
         assert!(matches.is_ok());
-        assert_eq!(matches.unwrap().len(), 2);
+        assert_eq!(matches.unwrap().0, 2);
 
         // マッチを反転させた場合、残りの1行にマッチする
-        let matches = find_lines(Cursor::new(&text), &re2, true);
+        let matches = find_lines(Cursor::new(&text), &re2, true, 0, 0);
         assert!(matches.is_ok());
-        assert_eq!(matches.unwrap().len(), 1);
+        assert_eq!(matches.unwrap().0, 1);
+    }
+
+    #[test]
+    fn test_find_lines_context() {
+        let text = b"one\ntwo\nMATCH\nfour\nfive\nsix\nMATCH\neight";
+        let re = Regex::new("MATCH").unwrap();
+
+        let (match_count, lines) = find_lines(Cursor::new(&text), &re, false, 1, 1).unwrap();
+        assert_eq!(match_count, 2);
+
+        let rendered: Vec<String> = lines
+            .iter()
+            .map(|l| match l {
+                OutputLine::Match(n, text) => format!("{}:{}", n, text.trim_end()),
+                OutputLine::Context(n, text) => format!("{}-{}", n, text.trim_end()),
+                OutputLine::Separator => "--".to_string(),
+            })
+            .collect();
+        assert_eq!(
+            rendered,
+            vec![
+                "2-two",
+                "3:MATCH",
+                "4-four",
+                "--",
+                "6-six",
+                "7:MATCH",
+                "8-eight",
+            ]
+        );
     }
 }